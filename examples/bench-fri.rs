@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+use cover_circuit::{index_secret, Clock};
+use plonky2::plonk::circuit_data::CircuitConfig;
+use tracing::info;
+
+// proving time, verifying time and proof size of an update with two FRI presets of the config that
+// is passed to `Clock::genesis`
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+    let standard = CircuitConfig::standard_ecc_config();
+    // higher blowup with less query rounds, for the same 100 security bits
+    let mut fast = CircuitConfig::standard_ecc_config();
+    fast.fri_config.rate_bits = 4;
+    fast.fri_config.num_query_rounds = 21;
+
+    const S: usize = 1 << 4;
+    let keys = [(); S].map({
+        let mut i = 0;
+        move |()| {
+            let secret = index_secret(i);
+            i += 1;
+            cover_circuit::public_key(secret)
+        }
+    });
+
+    for (name, config) in [("standard", standard), ("fast", fast)] {
+        let start = Instant::now();
+        let (genesis, circuit) = Clock::<S>::genesis(keys, config)?;
+        info!("{name} genesis {:?}", start.elapsed());
+        let start = Instant::now();
+        let clock = genesis.increment(0, index_secret(0), &circuit)?;
+        info!("{name} update {:?}", start.elapsed());
+        let start = Instant::now();
+        clock.verify(&circuit)?;
+        info!("{name} verify {:?}", start.elapsed());
+        info!("{name} proof size {} bytes", clock.to_bytes().len())
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::time::Instant;
+
+use cover_circuit::{index_secret, Clock};
+use plonky2::plonk::circuit_data::CircuitConfig;
+use tracing::info;
+
+// `Clock::genesis`, which overlaps the circuit building with the proving, versus the sequential one
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+    let config = CircuitConfig::standard_ecc_config();
+
+    const S: usize = 1 << 4;
+    let keys = [(); S].map({
+        let mut i = 0;
+        move |()| {
+            let secret = index_secret(i);
+            i += 1;
+            cover_circuit::public_key(secret)
+        }
+    });
+
+    let start = Instant::now();
+    let (clock1, _) = Clock::<S>::genesis_sequential(keys, config.clone())?;
+    info!("sequential genesis {:?}", start.elapsed());
+    let start = Instant::now();
+    let (clock2, _) = Clock::<S>::genesis(keys, config)?;
+    info!("overlapped genesis {:?}", start.elapsed());
+    // `standard_ecc_config` has no zero knowledge, so the proofs are identical
+    assert_eq!(clock1.to_bytes(), clock2.to_bytes());
+    Ok(())
+}
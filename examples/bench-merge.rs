@@ -0,0 +1,41 @@
+use std::time::Instant;
+
+use cover_circuit::{index_secret, merge::MergeCircuit, Clock};
+use plonky2::plonk::circuit_data::CircuitConfig;
+use tracing::info;
+
+// merging 4 clocks with one `MergeCircuit` proof versus 3 chained two-way merges
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+    let config = CircuitConfig::standard_ecc_config();
+
+    const S: usize = 1 << 4;
+    let (genesis, circuit) = Clock::<S>::genesis(
+        [(); S].map({
+            let mut i = 0;
+            move |()| {
+                let secret = index_secret(i);
+                i += 1;
+                cover_circuit::public_key(secret)
+            }
+        }),
+        config.clone(),
+    )?;
+    let clocks = (0..4)
+        .map(|index| genesis.update(index, index_secret(index), &genesis, &circuit))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let start = Instant::now();
+    let mut chained = clocks[0].clone();
+    for clock in &clocks[1..] {
+        chained = chained.merge(clock, &circuit)?
+    }
+    info!("chained two-way merges {:?}", start.elapsed());
+
+    let merge_circuit = MergeCircuit::new(&circuit, 4, config)?;
+    let start = Instant::now();
+    let merged = clocks[0].merge_many(&[&clocks[1], &clocks[2], &clocks[3]], &merge_circuit)?;
+    info!("one 4-way merge {:?}", start.elapsed());
+    assert_eq!(chained.snapshot(), merged.snapshot());
+    Ok(())
+}
@@ -0,0 +1,191 @@
+// runtime-sized counterparts of `Clock` and `ClockCircuit`, for systems that discover the number of
+// tracked nodes at runtime
+// the circuits are identical to the const generic ones with the same size, so proofs are
+// interchangeable between the two flavors. the clock operations are shared with `Clock` as well,
+// see `ClockView`
+
+use std::fmt::Debug;
+
+use plonky2::{
+    hash::hash_types::HashOut,
+    plonk::{
+        circuit_data::{CircuitConfig, CircuitData},
+        proof::ProofWithPublicInputs,
+    },
+};
+
+use crate::{
+    circuit_data, genesis_circuit_data, genesis_proof, merge_or_skip, update_proof, CircuitView,
+    ClockCircuitTargets, ClockError, ClockView, C, D, F,
+};
+
+#[derive(Clone)]
+pub struct DynClock {
+    pub counters_len: usize,
+    pub proof: ProofWithPublicInputs<F, C, D>,
+}
+
+impl Debug for DynClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.view(), f)
+    }
+}
+
+impl DynClock {
+    fn view(&self) -> ClockView<'_> {
+        ClockView {
+            name: "DynClock",
+            s: self.counters_len,
+            proof: &self.proof,
+        }
+    }
+
+    pub fn counters(&self) -> impl Iterator<Item = u32> + '_ {
+        self.view().counters()
+    }
+}
+
+#[derive(Debug)]
+pub struct DynClockCircuit {
+    pub s: usize,
+    pub data: CircuitData<F, C, D>,
+    pub(crate) targets: Option<ClockCircuitTargets>,
+}
+
+impl DynClockCircuit {
+    pub fn new_genesis(s: usize, config: CircuitConfig) -> Self {
+        Self {
+            s,
+            data: genesis_circuit_data(s, config),
+            targets: None,
+        }
+    }
+
+    pub fn new(inner: &Self, keys: &[HashOut<F>], config: CircuitConfig) -> anyhow::Result<Self> {
+        Self::new_internal(inner, keys, config, false)
+    }
+
+    // see `ClockCircuit::new_with_updated_index`
+    pub(crate) fn new_internal(
+        inner: &Self,
+        keys: &[HashOut<F>],
+        config: CircuitConfig,
+        reveal_index: bool,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            keys.len() == inner.s,
+            "expect {} keys, got {}",
+            inner.s,
+            keys.len()
+        );
+        let (data, targets) = circuit_data(&inner.data, keys, config, reveal_index)?;
+        Ok(Self {
+            s: inner.s,
+            data,
+            targets: Some(targets),
+        })
+    }
+
+    pub(crate) fn view(&self) -> CircuitView<'_> {
+        CircuitView {
+            s: self.s,
+            data: &self.data,
+            targets: self.targets.as_ref(),
+        }
+    }
+}
+
+impl DynClock {
+    pub fn genesis(
+        keys: &[HashOut<F>],
+        config: CircuitConfig,
+    ) -> Result<(Self, DynClockCircuit), ClockError> {
+        let (proof, circuit) = genesis_proof("DynClock", keys, config, false, true)?;
+        let clock = Self {
+            counters_len: keys.len(),
+            proof,
+        };
+        Ok((clock, circuit))
+    }
+
+    // merge with `other`, skipping the proving if either clock dominates the other one
+    pub fn merge(&self, other: &Self, circuit: &DynClockCircuit) -> Result<Self, ClockError> {
+        let proof = merge_or_skip(self.view(), other.view(), circuit.view())?;
+        Ok(Self {
+            counters_len: circuit.s,
+            proof,
+        })
+    }
+
+    pub fn update(
+        &self,
+        index: usize,
+        secret: F,
+        other: &Self,
+        circuit: &DynClockCircuit,
+    ) -> Result<Self, ClockError> {
+        let proof = update_proof(
+            self.view(),
+            Some(other.view()),
+            index,
+            secret,
+            circuit.view(),
+        )?;
+        Ok(Self {
+            counters_len: circuit.s,
+            proof,
+        })
+    }
+
+    // update without merging another clock, see `Clock::increment`
+    pub fn increment(
+        &self,
+        index: usize,
+        secret: F,
+        circuit: &DynClockCircuit,
+    ) -> Result<Self, ClockError> {
+        let proof = update_proof(self.view(), None, index, secret, circuit.view())?;
+        Ok(Self {
+            counters_len: circuit.s,
+            proof,
+        })
+    }
+
+    pub fn verify(&self, circuit: &DynClockCircuit) -> Result<(), ClockError> {
+        self.view().verify(circuit.view())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{index_secret, public_key};
+
+    use super::*;
+
+    #[test]
+    fn runtime_size() -> anyhow::Result<()> {
+        let s = 8;
+        let keys = (0..s)
+            .map(|i| public_key(index_secret(i)))
+            .collect::<Vec<_>>();
+        let (genesis, circuit) = DynClock::genesis(&keys, CircuitConfig::standard_ecc_config())?;
+        let clock1 = genesis.update(7, index_secret(7), &genesis, &circuit)?;
+        let clock2 = genesis.update(3, index_secret(3), &genesis, &circuit)?;
+        let clock = clock1.merge(&clock2, &circuit)?;
+        clock.verify(&circuit)?;
+        assert_eq!(
+            clock.counters().collect::<Vec<_>>(),
+            [0, 0, 0, 1, 0, 0, 0, 1]
+        );
+        let clock = clock.increment(3, index_secret(3), &circuit)?;
+        assert_eq!(format!("{clock:?}"), "DynClock {3: 2, 7: 1}");
+        // dominated, the same as `Clock::merge`
+        let merged = clock.merge(&clock1, &circuit)?;
+        assert_eq!(merged.proof, clock.proof);
+        assert!(matches!(
+            clock.increment(8, index_secret(8), &circuit),
+            Err(ClockError::OutOfBoundsIndex(8))
+        ));
+        Ok(())
+    }
+}
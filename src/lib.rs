@@ -1,7 +1,22 @@
+pub mod dynamic;
+pub mod merge;
+pub mod pool;
+pub mod rate;
 pub mod ser;
+pub mod sparse;
+pub mod threshold;
 
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    ops::Range,
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::Instant,
+};
 
+use anyhow::Context as _;
+use dynamic::DynClockCircuit;
 use plonky2::{
     field::types::{Field, PrimeField64},
     hash::{
@@ -10,7 +25,8 @@ use plonky2::{
         poseidon::{PoseidonHash, PoseidonPermutation},
     },
     iop::{
-        target::Target,
+        generator::generate_partial_witness,
+        target::{BoolTarget, Target},
         witness::{PartialWitness, WitnessWrite},
     },
     plonk::{
@@ -22,11 +38,18 @@ use plonky2::{
     },
     util::timing::TimingTree,
 };
+use plonky2_maybe_rayon::rayon;
 use plonky2_u32::gadgets::{
     arithmetic_u32::U32Target, multiple_comparison::list_le_u32_circuit,
     range_check::range_check_u32_circuit,
 };
 
+// the config is fixed crate-wide instead of being a type parameter of `Clock` and `ClockCircuit`
+// `ClockCircuit::new` verifies the inner proofs in circuit, which requires an algebraic hasher i.e.
+// `C::Hasher: AlgebraicHasher<F>`, so `KeccakGoldilocksConfig` does not work for the recursion
+// (it only suits the outermost layer of a wrapping proof, which this crate does not do). the u32
+// gadgets additionally assume the Goldilocks field with `D = 2`. `PoseidonGoldilocksConfig` is the
+// only config that is verified to work
 pub const D: usize = 2;
 pub type C = PoseidonGoldilocksConfig;
 pub type F = <C as GenericConfig<D>>::F;
@@ -38,34 +61,110 @@ pub struct Clock<const S: usize> {
 
 impl<const S: usize> Debug for Clock<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let counters = self
-            .counters()
-            .enumerate()
-            .filter(|(_, counter)| *counter != 0)
-            .collect::<HashMap<_, _>>();
-        write!(f, "Clock {:?}", counters)
+        Debug::fmt(&self.view(), f)
+    }
+}
+
+// equality and hashing are by the counters only, so clocks with the same causal value compare equal
+// even if they come with different proofs e.g. from different merging orders
+// the proof validity must be checked separately with `verify`
+impl<const S: usize> PartialEq for Clock<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.counters().eq(other.counters())
+    }
+}
+
+impl<const S: usize> Eq for Clock<S> {}
+
+impl<const S: usize> Hash for Clock<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.snapshot().hash(state)
     }
 }
 
 impl<const S: usize> Clock<S> {
+    fn view(&self) -> ClockView<'_> {
+        ClockView {
+            name: "Clock",
+            s: S,
+            proof: &self.proof,
+        }
+    }
+
     pub fn counters(&self) -> impl Iterator<Item = u32> + '_ {
-        self.proof
-            .public_inputs
-            .iter()
-            .take(S)
-            .map(|counter| counter.to_canonical_u64() as _)
+        self.view().counters()
+    }
+
+    // all counters on stack, without allocation
+    pub fn snapshot(&self) -> [u32; S] {
+        let mut snapshot = [0; S];
+        for (snapshot_counter, counter) in snapshot.iter_mut().zip(self.counters()) {
+            *snapshot_counter = counter
+        }
+        snapshot
+    }
+
+    // compare with the counters only, without verifying either clock
+    pub fn partial_cmp_causal(&self, other: &Self) -> Option<Ordering> {
+        self.view().partial_cmp(other.view())
+    }
+
+    // (index, advanced amount) of every counter that `newer` is ahead of this clock, from the
+    // public inputs only
+    pub fn diff(&self, newer: &Self) -> Vec<(usize, u32)> {
+        self.counters()
+            .zip(newer.counters())
+            .enumerate()
+            .filter(|(_, (counter, newer_counter))| newer_counter > counter)
+            .map(|(index, (counter, newer_counter))| (index, newer_counter - counter))
+            .collect()
+    }
+
+    // element-wise maximum of the counters of `clocks`, i.e. the counters that merging them results in,
+    // without proving or verifying anything
+    pub fn join_counters(clocks: &[&Self]) -> [u32; S] {
+        let mut joined = [0; S];
+        for clock in clocks {
+            for (joined_counter, counter) in joined.iter_mut().zip(clock.counters()) {
+                *joined_counter = (*joined_counter).max(counter)
+            }
+        }
+        joined
+    }
+
+    // the index that is incremented by the proving of this clock, or `None` if it is produced by
+    // merging (or genesis), or by a circuit that does not reveal the updated index, see
+    // `ClockCircuit::new_with_updated_index`
+    pub fn last_updated_index(&self) -> Option<usize> {
+        let index = self.proof.public_inputs.get(S)?.to_canonical_u64() as usize;
+        if index < S {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    // whether the key of the last updated index is in `allowed`, where `keys` are the ones that the
+    // verifying circuit is built with
+    // a clock produced by merging is not signed by any of the keys, and is always authorized, and so
+    // is every clock of a circuit that does not reveal the updated index
+    // this is an off-circuit policy check on top of `verify`, not a replacement of it
+    pub fn check_update_authorized(&self, keys: &[HashOut<F>; S], allowed: &[HashOut<F>]) -> bool {
+        self.last_updated_index()
+            .map_or(true, |index| allowed.contains(&keys[index]))
     }
 }
 
 #[derive(Debug)]
 pub struct ClockCircuit<const S: usize> {
     pub data: CircuitData<F, C, D>,
-    targets: Option<ClockCircuitTargets<S>>,
+    targets: Option<ClockCircuitTargets>,
 }
 
 #[derive(Debug)]
-struct ClockCircuitTargets<const S: usize> {
-    // the only public input is the output clock, which is not expected to be set before proving
+struct ClockCircuitTargets {
+    // the public inputs are the output clock followed by `updated_index`, which are not expected to
+    // be set before proving
     // every target is witness
 
     // common inputs
@@ -77,19 +176,131 @@ struct ClockCircuitTargets<const S: usize> {
     updated_counter: Target, // ...F::NEG_ONE
     sig: Target,             // ...sign F::NEG_ONE with DUMMY_KEY
 
-    // enable2: BoolTarget,
-    // merge inputs, when incrementing...
-    proof2: ProofWithPublicInputsTarget<D>, // ...same to `proof1`
+    // merge inputs, when incrementing without merging...
+    enable2: BoolTarget,                    // ...false
+    proof2: ProofWithPublicInputsTarget<D>, // ...any proof of the inner circuit e.g. `proof1`
     verifier_data2: VerifierCircuitTarget,  // ...same to `verifier_data1`
 }
 
+// the circuit building and proving are not specific to the const generic `S`, so they are shared
+// between `Clock` and `dynamic::DynClock`, with the clock size `s` passed in as runtime value
+
+fn genesis_circuit_data(s: usize, config: CircuitConfig) -> CircuitData<F, C, D> {
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+    let output_counters = builder.constants(&vec![F::ZERO; s]);
+    builder.register_public_inputs(&output_counters);
+    builder.build()
+}
+
+fn circuit_data(
+    inner: &CircuitData<F, C, D>,
+    keys: &[HashOut<F>],
+    config: CircuitConfig,
+    reveal_index: bool,
+) -> anyhow::Result<(CircuitData<F, C, D>, ClockCircuitTargets)> {
+    let dummy_key = dummy_public_key();
+    // otherwise a merge, which is signed with the dummy secret, could masquerade as an update
+    if let Some(index) = keys.iter().position(|key| *key == dummy_key) {
+        anyhow::bail!("key {index} is the dummy key")
+    }
+    let s = keys.len();
+    let mut builder = CircuitBuilder::<F, D>::new(config);
+
+    let proof1 = builder.add_virtual_proof_with_pis(&inner.common);
+    // skip the trailing `updated_index` of the inner proof
+    let input_counters1 = &proof1.public_inputs[..s];
+    let proof2 = builder.add_virtual_proof_with_pis(&inner.common);
+    let input_counters2 = &proof2.public_inputs[..s];
+
+    let updated_index = builder.add_virtual_target();
+    let updated_counter = builder.add_virtual_target();
+    // although there is `add_virtual_nonnative_target`, but seems like currently it can only be
+    // internally used by generators. there's no `set_nonnative_target` companion for witness
+    // let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<Secp256K1Scalar>();
+    let sig = builder.add_virtual_target();
+    let enable2 = builder.add_virtual_bool_target_safe();
+
+    let verifier_data1 =
+        builder.add_virtual_verifier_data(inner.common.config.fri_config.cap_height);
+    builder.verify_proof::<C>(&proof1, &verifier_data1, &inner.common);
+    let verifier_data2 =
+        builder.add_virtual_verifier_data(inner.common.config.fri_config.cap_height);
+    // a disabled `proof2` falls back to verifying `proof1` again, instead of a dummy proof, which
+    // requires a non zero knowledge config and proving the dummy circuit on every circuit building
+    builder.conditionally_verify_proof::<C>(
+        enable2,
+        &proof2,
+        &verifier_data2,
+        &proof1,
+        &verifier_data1,
+        &inner.common,
+    );
+
+    let mut updated_key = builder.constant_hash(dummy_key);
+
+    let output_counters = input_counters1
+        .iter()
+        .zip(input_counters2)
+        .enumerate()
+        .map(|(i, (input_counter1, input_counter2))| {
+            let key = keys[i];
+            let i = builder.constant(F::from_canonical_usize(i));
+            let is_updated = builder.is_equal(updated_index, i);
+            let x1 = U32Target(builder.select(is_updated, updated_counter, *input_counter1));
+
+            let key = builder.constant_hash(key);
+            let elements = updated_key
+                .elements
+                .iter()
+                .zip(&key.elements)
+                .map(|(updated_target, target)| {
+                    builder.select(is_updated, *target, *updated_target)
+                })
+                .collect();
+            updated_key = HashOutTarget::from_vec(elements);
+
+            // a disabled `proof2` contributes zero counters
+            let x2 = U32Target(builder.mul(enable2.target, *input_counter2));
+            range_check_u32_circuit(&mut builder, vec![x1, x2]);
+            // max(x1, x2)
+            let le = list_le_u32_circuit(&mut builder, vec![x1], vec![x2]);
+            let U32Target(x1) = x1;
+            let U32Target(x2) = x2;
+            builder.select(le, x2, x1)
+        })
+        .collect::<Vec<_>>();
+
+    // let msg = builder.biguint_to_nonnative::<Secp256K1Scalar>(&updated_counter);
+    // verify_message_circuit(&mut builder, msg, sig, ECDSAPublicKeyTarget(updated_key));
+    let key = public_key_circuit(&mut builder, [sig]);
+    builder.connect_hashes(key, updated_key);
+
+    builder.register_public_inputs(&output_counters);
+    // reveal which index is updated (without revealing `sig`) for accountability
+    // opt-in, as the extra public input changes the circuit and the proofs of it. either way the
+    // circuit differs from the one before the optional second input (`enable2`), so proofs from
+    // before that e.g. an old genesis_clock4.bin of examples/precompute.rs must be regenerated
+    if reveal_index {
+        builder.register_public_input(updated_index)
+    }
+    // builder.print_gate_counts(0);
+    let targets = ClockCircuitTargets {
+        proof1,
+        verifier_data1,
+        proof2,
+        verifier_data2,
+        updated_index,
+        updated_counter,
+        sig,
+        enable2,
+    };
+    Ok((builder.build(), targets))
+}
+
 impl<const S: usize> ClockCircuit<S> {
     pub fn new_genesis(config: CircuitConfig) -> Self {
-        let mut builder = CircuitBuilder::<F, D>::new(config);
-        let output_counters = builder.constants(&[F::ZERO; S]);
-        builder.register_public_inputs(&output_counters);
         Self {
-            data: builder.build(),
+            data: genesis_circuit_data(S, config),
             targets: None,
         }
     }
@@ -97,91 +308,32 @@ impl<const S: usize> ClockCircuit<S> {
     pub fn new(
         inner: &Self,
         keys: &[HashOut<F>; S],
-        dummy_key: HashOut<F>,
         config: CircuitConfig,
-    ) -> Self {
-        let mut builder = CircuitBuilder::<F, D>::new(config);
-
-        let proof1 = builder.add_virtual_proof_with_pis(&inner.data.common);
-        // the slicing is not necessary for now, just in case we add more public inputs later
-        let input_counters1 = &proof1.public_inputs[..S];
-        let proof2 = builder.add_virtual_proof_with_pis(&inner.data.common);
-        let input_counters2 = &proof2.public_inputs[..S];
-
-        let updated_index = builder.add_virtual_target();
-        let updated_counter = builder.add_virtual_target();
-        // although there is `add_virtual_nonnative_target`, but seems like currently it can only be
-        // internally used by generators. there's no `set_nonnative_target` companion for witness
-        // let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<Secp256K1Scalar>();
-        let sig = builder.add_virtual_target();
-
-        let verifier_data1 =
-            builder.add_virtual_verifier_data(inner.data.common.config.fri_config.cap_height);
-        builder.verify_proof::<C>(&proof1, &verifier_data1, &inner.data.common);
-        let verifier_data2 =
-            builder.add_virtual_verifier_data(inner.data.common.config.fri_config.cap_height);
-        builder.verify_proof::<C>(&proof2, &verifier_data2, &inner.data.common);
-        // let enable2 = builder.add_virtual_bool_target_safe();
-        // builder.conditionally_verify_proof_or_dummy::<C>(
-        //     enable2,
-        //     &proof2,
-        //     &verifier_data2,
-        //     &inner.data.common,
-        // )?;
-
-        let mut updated_key = builder.constant_hash(dummy_key);
-
-        let output_counters = input_counters1
-            .iter()
-            .zip(input_counters2)
-            .enumerate()
-            .map(|(i, (input_counter1, input_counter2))| {
-                let key = keys[i];
-                let i = builder.constant(F::from_canonical_usize(i));
-                let is_updated = builder.is_equal(updated_index, i);
-                let x1 = U32Target(builder.select(is_updated, updated_counter, *input_counter1));
-
-                let key = builder.constant_hash(key);
-                let elements = updated_key
-                    .elements
-                    .iter()
-                    .zip(&key.elements)
-                    .map(|(updated_target, target)| {
-                        builder.select(is_updated, *target, *updated_target)
-                    })
-                    .collect();
-                updated_key = HashOutTarget::from_vec(elements);
-
-                let x2 = U32Target(*input_counter2);
-                range_check_u32_circuit(&mut builder, vec![x1, x2]);
-                // max(x1, x2)
-                let le = list_le_u32_circuit(&mut builder, vec![x1], vec![x2]);
-                let U32Target(x1) = x1;
-                let U32Target(x2) = x2;
-                builder.select(le, x2, x1)
-            })
-            .collect::<Vec<_>>();
+    ) -> anyhow::Result<Self> {
+        Self::new_internal(inner, keys, config, false)
+    }
 
-        // let msg = builder.biguint_to_nonnative::<Secp256K1Scalar>(&updated_counter);
-        // verify_message_circuit(&mut builder, msg, sig, ECDSAPublicKeyTarget(updated_key));
-        let key = builder.hash_n_to_hash_no_pad::<PoseidonHash>(vec![sig]);
-        builder.connect_hashes(key, updated_key);
+    // `new` that additionally reveals the updated index as the last public input, which is read by
+    // `Clock::last_updated_index`
+    pub fn new_with_updated_index(
+        inner: &Self,
+        keys: &[HashOut<F>; S],
+        config: CircuitConfig,
+    ) -> anyhow::Result<Self> {
+        Self::new_internal(inner, keys, config, true)
+    }
 
-        builder.register_public_inputs(&output_counters);
-        // builder.print_gate_counts(0);
-        Self {
-            data: builder.build(),
-            targets: Some(ClockCircuitTargets {
-                proof1,
-                verifier_data1,
-                proof2,
-                verifier_data2,
-                updated_index,
-                updated_counter,
-                sig,
-                // enable2,
-            }),
-        }
+    fn new_internal(
+        inner: &Self,
+        keys: &[HashOut<F>; S],
+        config: CircuitConfig,
+        reveal_index: bool,
+    ) -> anyhow::Result<Self> {
+        let (data, targets) = circuit_data(&inner.data, keys, config, reveal_index)?;
+        Ok(Self {
+            data,
+            targets: Some(targets),
+        })
     }
 
     pub fn with_data(data: CircuitData<F, C, D>, config: CircuitConfig) -> Self {
@@ -190,9 +342,51 @@ impl<const S: usize> ClockCircuit<S> {
             data,
         }
     }
+
+    // for verifying only, without building the proving targets
+    // `update`, `increment` and `merge` return error against the returned circuit
+    pub fn verifier_only(data: CircuitData<F, C, D>) -> Self {
+        Self {
+            data,
+            targets: None,
+        }
+    }
+
+    fn view(&self) -> CircuitView<'_> {
+        CircuitView {
+            s: S,
+            data: &self.data,
+            targets: self.targets.as_ref(),
+        }
+    }
+
+    // verify clocks in order and stop at the first failure
+    // plonky2 verifier takes proofs by value, so each proof is still cloned
+    pub fn verify_batch(&self, clocks: &[&Clock<S>]) -> anyhow::Result<()> {
+        for (index, clock) in clocks.iter().enumerate() {
+            self.data
+                .verify(clock.proof.clone())
+                .map_err(|err| anyhow::anyhow!("clock {index} in batch: {err}"))?
+        }
+        Ok(())
+    }
+
+    pub fn check_witness(&self, pw: &PartialWitness<F>) -> anyhow::Result<()> {
+        check_witness(&self.data, pw)
+    }
+
+    // named regions of the public inputs of proofs of this circuit, in order
+    pub fn public_input_layout(&self) -> Vec<(&'static str, Range<usize>)> {
+        let mut layout = vec![("counters", 0..S)];
+        // the genesis circuit, and the ones built with `new`, have no updated index
+        if self.data.common.num_public_inputs > S {
+            layout.push(("updated_index", S..S + 1))
+        }
+        layout
+    }
 }
 
-impl<const S: usize> ClockCircuitTargets<S> {
+impl ClockCircuitTargets {
     fn new(circuit: &CircuitData<F, C, D>, config: CircuitConfig) -> Self {
         let mut builder = CircuitBuilder::new(config);
         // let num_limbs = CircuitBuilder::<F, D>::num_nonnative_limbs::<Secp256K1Scalar>();
@@ -202,46 +396,556 @@ impl<const S: usize> ClockCircuitTargets<S> {
             updated_index: builder.add_virtual_target(),
             updated_counter: builder.add_virtual_target(),
             sig: builder.add_virtual_target(),
+            enable2: builder.add_virtual_bool_target_safe(),
             verifier_data1: builder
                 .add_virtual_verifier_data(circuit.common.config.fri_config.cap_height),
             verifier_data2: builder
                 .add_virtual_verifier_data(circuit.common.config.fri_config.cap_height),
         }
     }
+
+    // prove with `proof1` and optionally `proof2` of the `inner` circuit
+    // when `proof2` is absent it is not verified, but its targets still need to be filled with some
+    // proof of the inner circuit, so `proof1` is reused
+    #[allow(clippy::too_many_arguments)]
+    fn prove(
+        &self,
+        kind: &'static str,
+        circuit: CircuitView,
+        inner: &CircuitData<F, C, D>,
+        proof1: &ProofWithPublicInputs<F, C, D>,
+        proof2: Option<&ProofWithPublicInputs<F, C, D>>,
+        updated_index: usize,
+        updated_counter: u32,
+        sig: F,
+    ) -> Result<ProofWithPublicInputs<F, C, D>, ClockError> {
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&self.proof1, proof1);
+        pw.set_verifier_data_target(&self.verifier_data1, &inner.verifier_only);
+        pw.set_proof_with_pis_target(&self.proof2, proof2.unwrap_or(proof1));
+        pw.set_verifier_data_target(&self.verifier_data2, &inner.verifier_only);
+        pw.set_target(self.updated_index, F::from_canonical_usize(updated_index));
+        pw.set_target(self.updated_counter, F::from_canonical_u32(updated_counter));
+        // let msg = Secp256K1Scalar::from_canonical_u32(u32::MAX);
+        // let sig = sign_message(msg, DUMMY_SECRET);
+        pw.set_target(self.sig, sig);
+        pw.set_bool_target(self.enable2, proof2.is_some());
+        // reject e.g. a wrong secret before spending the proving effort on it, during development
+        // only, as the witness generation is run again by the proving. in release builds the
+        // conflict is caught by `prove_traced` instead, and `ClockCircuit::check_witness` is for
+        // running the check explicitly
+        if cfg!(debug_assertions) {
+            check_witness(circuit.data, &pw).map_err(ClockError::WitnessInconsistent)?
+        }
+        prove_traced(
+            kind,
+            circuit.s,
+            Some(updated_index).filter(|index| *index < circuit.s),
+            circuit.data,
+            pw,
+        )
+        .map_err(ClockError::ProvingFailed)
+    }
+}
+
+// prove inside a `prove` span, so the proving work shows up in the traces of the surrounding
+// context. `TimingTree` reports through `log`, so the total elapsed time is also recorded on the
+// span
+fn prove_traced(
+    kind: &'static str,
+    s: usize,
+    index: Option<usize>,
+    circuit: &CircuitData<F, C, D>,
+    pw: PartialWitness<F>,
+) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+    let span = tracing::info_span!(
+        "prove",
+        kind,
+        s,
+        index = tracing::field::Empty,
+        elapsed = tracing::field::Empty
+    );
+    if let Some(index) = index {
+        span.record("index", index);
+    }
+    let _enter = span.enter();
+    let start = Instant::now();
+    let mut timing = TimingTree::new(
+        &format!("prove {kind}"),
+        "INFO".parse().map_err(anyhow::Error::msg)?,
+    );
+    // the witness generation inside `prove` panics on conflicting values, which is not always caught
+    // by `check_witness` beforehand e.g. for the genesis and threshold circuits, and a public method
+    // should never panic on a bad input
+    let proof = catch_unwind(AssertUnwindSafe(|| {
+        prove(&circuit.prover_only, &circuit.common, pw, &mut timing)
+    }))
+    .map_err(|err| anyhow::anyhow!("proving panicked: {}", panic_message(&*err)))??;
+    timing.print();
+    span.record("elapsed", tracing::field::debug(start.elapsed()));
+    Ok(proof)
+}
+
+// run the witness generation without proving
+// an inconsistent witness e.g. a secret that does not match the updated index results in
+// conflicting values of connected targets, which is detected here in a fraction of the proving time
+// the gate constraints are NOT evaluated. most generators satisfy them by construction, but not all
+// of them, e.g. the generator of `U32RangeCheckGate` decomposes the value without checking its range,
+// so an out of range counter passes this check. such a witness results in a proof that fails
+// verification, which the callers of proving always run on the proved clock
+fn check_witness(circuit: &CircuitData<F, C, D>, pw: &PartialWitness<F>) -> anyhow::Result<()> {
+    // the witness generation panics instead of returning error on conflicts
+    catch_unwind(AssertUnwindSafe(|| {
+        generate_partial_witness(pw.clone(), &circuit.prover_only, &circuit.common);
+    }))
+    .map_err(|err| anyhow::anyhow!("inconsistent witness: {}", panic_message(&*err)))
 }
 
+fn panic_message(err: &(dyn std::any::Any + Send)) -> &str {
+    err.downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| err.downcast_ref::<&str>().copied())
+        .unwrap_or("unknown error")
+}
+
+// check that the output counters are the element-wise max of the input ones, except the
+// `updated` index which is set to the updated counter
+fn check_output_counters(
+    output_counters: impl Iterator<Item = u32>,
+    input_counters1: impl Iterator<Item = u32>,
+    input_counters2: impl Iterator<Item = u32>,
+    updated: Option<(usize, u32)>,
+) -> anyhow::Result<()> {
+    for (i, ((output_counter, input_counter1), input_counter2)) in output_counters
+        .zip(input_counters1)
+        .zip(input_counters2)
+        .enumerate()
+    {
+        let expected = match updated {
+            Some((index, counter)) if index == i => counter,
+            _ => input_counter1.max(input_counter2),
+        };
+        anyhow::ensure!(
+            output_counter == expected,
+            "output counter {i} = {output_counter}, expected {expected}"
+        )
+    }
+    Ok(())
+}
+
+// INSECURE, for tests only, see `Clock::genesis_fast`
+#[cfg(any(test, feature = "insecure-fast-genesis"))]
+pub fn insecure_test_config() -> CircuitConfig {
+    let mut config = CircuitConfig::standard_ecc_config();
+    config.security_bits = 0;
+    config.fri_config.num_query_rounds = 2;
+    config.fri_config.proof_of_work_bits = 0;
+    config
+}
+
+// the secret that signs merges, whose public key is the initial `updated_key` in the circuit, i.e.
+// the expected key when no index is updated
 const DUMMY_SECRET: F = F::NEG_ONE;
 
+pub fn dummy_public_key() -> HashOut<F> {
+    public_key(DUMMY_SECRET)
+}
+
+// the error of `Clock::genesis`, `update`, `increment`, `merge` and `verify`, for callers that
+// branch on the kind of failure. the rest of the crate works with `anyhow`, which this converts into
+#[derive(Debug)]
+pub enum ClockError {
+    OutOfBoundsIndex(usize),
+    // the counter at the index is `u32::MAX` and cannot be incremented
+    CounterSaturated(usize),
+    // the circuit cannot be built e.g. with the dummy key among the keys, or cannot prove e.g. the
+    // genesis or verifier only circuit
+    InvalidCircuit(anyhow::Error),
+    ProvingFailed(anyhow::Error),
+    // including the input clocks of proving, and the sanity checks on the proved clock
+    VerificationFailed(anyhow::Error),
+    // e.g. a secret that does not match the updated index, detected before proving in debug builds,
+    // see `check_witness`
+    WitnessInconsistent(anyhow::Error),
+}
+
+impl std::fmt::Display for ClockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfBoundsIndex(index) => write!(f, "out of bound index {index}"),
+            Self::CounterSaturated(index) => write!(f, "counter at index {index} saturated"),
+            // the whole chain of the wrapped error is printed here, so it is not exposed as
+            // `source` as well, which chain reporters would print twice
+            Self::InvalidCircuit(err) => write!(f, "invalid circuit: {err:#}"),
+            Self::ProvingFailed(err) => write!(f, "proving failed: {err:#}"),
+            Self::VerificationFailed(err) => write!(f, "verification failed: {err:#}"),
+            // `check_witness` has prefixed the message
+            Self::WitnessInconsistent(err) => write!(f, "{err:#}"),
+        }
+    }
+}
+
+impl std::error::Error for ClockError {}
+
+// the clock operations are shared between `Clock` and `dynamic::DynClock` in the same way as the
+// circuit building, through the views below with the clock size `s` as runtime value, so the two
+// flavors cannot diverge
+
+#[derive(Clone, Copy)]
+struct ClockView<'a> {
+    // the type name in debug output
+    name: &'static str,
+    s: usize,
+    proof: &'a ProofWithPublicInputs<F, C, D>,
+}
+
+#[derive(Clone, Copy)]
+struct CircuitView<'a> {
+    s: usize,
+    data: &'a CircuitData<F, C, D>,
+    // `None` for the genesis and verifier only circuits
+    targets: Option<&'a ClockCircuitTargets>,
+}
+
+impl Debug for ClockView<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.name)?;
+        f.debug_map()
+            .entries(
+                self.counters()
+                    .enumerate()
+                    .filter(|(_, counter)| *counter != 0),
+            )
+            .finish()
+    }
+}
+
+impl<'a> ClockView<'a> {
+    fn counters(self) -> impl Iterator<Item = u32> + 'a {
+        self.proof
+            .public_inputs
+            .iter()
+            .take(self.s)
+            .map(|counter| counter.to_canonical_u64() as _)
+    }
+
+    fn counter(self, index: usize) -> Result<u32, ClockError> {
+        self.proof
+            .public_inputs
+            .get(index)
+            .filter(|_| index < self.s)
+            .map(|counter| counter.to_canonical_u64() as _)
+            .ok_or(ClockError::OutOfBoundsIndex(index))
+    }
+
+    fn partial_cmp(self, other: Self) -> Option<Ordering> {
+        let mut ordering = Ordering::Equal;
+        for (counter, other_counter) in self.counters().zip(other.counters()) {
+            match (ordering, counter.cmp(&other_counter)) {
+                (_, Ordering::Equal) => {}
+                (Ordering::Equal, counter_ordering) => ordering = counter_ordering,
+                (ordering, counter_ordering) if ordering != counter_ordering => return None,
+                _ => {}
+            }
+        }
+        Some(ordering)
+    }
+
+    fn verify(self, circuit: CircuitView) -> Result<(), ClockError> {
+        if self.s != circuit.s {
+            return Err(ClockError::VerificationFailed(anyhow::anyhow!(
+                "{self:?} of size {} against circuit of size {}",
+                self.s,
+                circuit.s
+            )));
+        }
+        circuit
+            .data
+            .verify(self.proof.clone())
+            .with_context(|| {
+                format!(
+                    "{self:?} failed verification against circuit of {} gates",
+                    circuit.data.common.degree()
+                )
+            })
+            .map_err(ClockError::VerificationFailed)
+    }
+
+    // check that every counter is no less than the ones of `parents`
+    fn check_succeeds(self, parents: &[Self]) -> anyhow::Result<()> {
+        for parent in parents {
+            // zipping skips the missing counters, which should not pass silently
+            anyhow::ensure!(
+                parent.proof.public_inputs.len() >= self.s,
+                "parent of {} public inputs, expected at least {}",
+                parent.proof.public_inputs.len(),
+                self.s
+            );
+            for (index, (counter, parent_counter)) in
+                self.counters().zip(parent.counters()).enumerate()
+            {
+                anyhow::ensure!(
+                    counter >= parent_counter,
+                    "counter {index} = {counter} is behind parent counter {parent_counter}"
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> CircuitView<'a> {
+    fn targets(self) -> Result<&'a ClockCircuitTargets, ClockError> {
+        self.targets
+            .ok_or(ClockError::InvalidCircuit(anyhow::anyhow!(
+                "proving with genesis or verifier only circuit"
+            )))
+    }
+}
+
+// the FRI config of `config` trades proving time for proof size e.g. higher `rate_bits` with less
+// query rounds for faster proving and verifying, or larger reduction arities for smaller proofs.
+// every clock circuit recursively verifies proofs of the same config, so it is only set at genesis
+// `degree_bits` is of the smallest circuit that is built with the config i.e. the genesis circuit
+fn check_fri_config(config: &CircuitConfig, degree_bits: usize) -> anyhow::Result<()> {
+    let fri_config = &config.fri_config;
+    anyhow::ensure!(fri_config.rate_bits > 0, "zero rate bits");
+    let security_bits =
+        fri_config.rate_bits * fri_config.num_query_rounds + fri_config.proof_of_work_bits as usize;
+    anyhow::ensure!(
+        security_bits >= config.security_bits,
+        "FRI config provides {security_bits} security bits, expected {}",
+        config.security_bits
+    );
+    let lde_bits = degree_bits + fri_config.rate_bits;
+    anyhow::ensure!(
+        fri_config.cap_height <= lde_bits,
+        "cap height {} exceeds LDE bits {lde_bits}",
+        fri_config.cap_height
+    );
+    Ok(())
+}
+
+// the genesis clock and the circuit that it is proved with, which is `DynClockCircuit` for both
+// flavors since `ClockCircuit` only adds the size into the type
+fn genesis_proof(
+    name: &'static str,
+    keys: &[HashOut<F>],
+    config: CircuitConfig,
+    reveal_index: bool,
+    overlap: bool,
+) -> Result<(ProofWithPublicInputs<F, C, D>, DynClockCircuit), ClockError> {
+    let s = keys.len();
+    let mut circuit = DynClockCircuit::new_genesis(s, config.clone());
+    check_fri_config(&config, circuit.data.common.degree_bits())
+        .map_err(ClockError::InvalidCircuit)?;
+    let mut proof = prove_traced("genesis", s, None, &circuit.data, PartialWitness::new())
+        .map_err(ClockError::ProvingFailed)?;
+
+    let mut inner_circuit = circuit;
+    circuit = DynClockCircuit::new_internal(&inner_circuit, keys, config.clone(), reveal_index)
+        .map_err(ClockError::InvalidCircuit)?;
+    for i in 0..4 {
+        // building the circuit of the next round only depends on the current circuit, so it
+        // overlaps with the proving of the current round. the proving saturates the thread pool
+        // most of the time, so the saving is bounded by the circuit building time
+        let build = || {
+            if i == 3 {
+                return Ok(None);
+            }
+            DynClockCircuit::new_internal(&circuit, keys, config.clone(), reveal_index).map(Some)
+        };
+        let prove = || {
+            let clock = ClockView {
+                name,
+                s,
+                proof: &proof,
+            };
+            merge_proof(clock, clock, circuit.view(), inner_circuit.view())
+        };
+        let (next_circuit, next_proof) = if overlap {
+            rayon::join(build, prove)
+        } else {
+            let next_proof = prove();
+            (build(), next_proof)
+        };
+        proof = next_proof?;
+        let Some(next_circuit) = next_circuit.map_err(ClockError::InvalidCircuit)? else {
+            break;
+        };
+        inner_circuit = std::mem::replace(&mut circuit, next_circuit)
+    }
+
+    let clock = ClockView {
+        name,
+        s,
+        proof: &proof,
+    };
+    if clock.counters().any(|counter| counter != 0) {
+        return Err(ClockError::VerificationFailed(anyhow::anyhow!(
+            "nonzero genesis counters {clock:?}"
+        )));
+    }
+    Ok((proof, circuit))
+}
+
+// merge clocks of the `inner` circuit, which is `circuit` itself except during genesis
+fn merge_proof(
+    clock1: ClockView,
+    clock2: ClockView,
+    circuit: CircuitView,
+    inner: CircuitView,
+) -> Result<ProofWithPublicInputs<F, C, D>, ClockError> {
+    // a malformed input proof conflicts with the witness generation of the recursive
+    // verification, which panics inside `prove`, so reject it beforehand
+    clock1.verify(inner)?;
+    clock2.verify(inner)?;
+    let proof = circuit.targets()?.prove(
+        "merge",
+        circuit,
+        inner.data,
+        clock1.proof,
+        Some(clock2.proof),
+        circuit.s + 1,
+        u32::MAX,
+        DUMMY_SECRET,
+    )?;
+    let clock = ClockView {
+        proof: &proof,
+        ..clock1
+    };
+    clock.verify(circuit)?;
+    clock
+        .check_succeeds(&[clock1, clock2])
+        .and_then(|()| {
+            check_output_counters(clock.counters(), clock1.counters(), clock2.counters(), None)
+        })
+        .map_err(ClockError::VerificationFailed)?;
+    Ok(proof)
+}
+
+// merge, skipping the proving if either clock dominates the other one
+// both clocks are verified on every path, so the result is never a clock with unproven counters
+fn merge_or_skip(
+    clock1: ClockView,
+    clock2: ClockView,
+    circuit: CircuitView,
+) -> Result<ProofWithPublicInputs<F, C, D>, ClockError> {
+    match clock1.partial_cmp(clock2) {
+        Some(ordering) => {
+            clock1.verify(circuit)?;
+            clock2.verify(circuit)?;
+            if ordering == Ordering::Less {
+                Ok(clock2.proof.clone())
+            } else {
+                Ok(clock1.proof.clone())
+            }
+        }
+        None => merge_proof(clock1, clock2, circuit, circuit),
+    }
+}
+
+// increment the counter at `index`, merging `clock2` if any
+fn update_proof(
+    clock1: ClockView,
+    clock2: Option<ClockView>,
+    index: usize,
+    secret: F,
+    circuit: CircuitView,
+) -> Result<ProofWithPublicInputs<F, C, D>, ClockError> {
+    let counter = clock1
+        .counter(index)?
+        .max(clock2.map_or(Ok(0), |clock2| clock2.counter(index))?)
+        // the circuit range checks the counters, so a wrapped counter would fail proving
+        .checked_add(1)
+        .ok_or(ClockError::CounterSaturated(index))?;
+    clock1.verify(circuit)?;
+    if let Some(clock2) = clock2 {
+        clock2.verify(circuit)?
+    }
+    let proof = circuit.targets()?.prove(
+        "update",
+        circuit,
+        circuit.data,
+        clock1.proof,
+        clock2.map(|clock2| clock2.proof),
+        index,
+        counter,
+        secret,
+    )?;
+    let clock = ClockView {
+        proof: &proof,
+        ..clock1
+    };
+    let mut parents = vec![clock1];
+    parents.extend(clock2);
+    clock.verify(circuit)?;
+    clock
+        .check_succeeds(&parents)
+        .and_then(|()| {
+            check_output_counters(
+                clock.counters(),
+                clock1.counters(),
+                // a disabled second input contributes zero counters
+                clock2
+                    .into_iter()
+                    .flat_map(ClockView::counters)
+                    .chain(std::iter::repeat(0)),
+                Some((index, counter)),
+            )
+        })
+        .map_err(ClockError::VerificationFailed)?;
+    Ok(proof)
+}
+
 impl<const S: usize> Clock<S> {
     pub fn genesis(
         keys: [HashOut<F>; S],
         config: CircuitConfig,
-    ) -> anyhow::Result<(Self, ClockCircuit<S>)> {
-        let mut circuit = ClockCircuit::new_genesis(config.clone());
-        let mut timing =
-            TimingTree::new("prove genesis", "INFO".parse().map_err(anyhow::Error::msg)?);
-        let proof = prove(
-            &circuit.data.prover_only,
-            &circuit.data.common,
-            PartialWitness::new(),
-            &mut timing,
-        )?;
-        timing.print();
-        let mut clock = Self {
-            proof,
-            // depth: 0
-        };
+    ) -> Result<(Self, ClockCircuit<S>), ClockError> {
+        Self::genesis_internal(keys, config, false, true)
+    }
 
-        let dummy_key = public_key(DUMMY_SECRET);
-        let mut inner_circuit = circuit;
-        for _ in 0..4 {
-            circuit = ClockCircuit::new(&inner_circuit, &keys, dummy_key, config.clone());
-            clock = clock.merge_internal(&clock, &circuit, &inner_circuit)?;
-            inner_circuit = circuit;
-        }
+    // `genesis` with the circuit of `ClockCircuit::new_with_updated_index`
+    pub fn genesis_with_updated_index(
+        keys: [HashOut<F>; S],
+        config: CircuitConfig,
+    ) -> Result<(Self, ClockCircuit<S>), ClockError> {
+        Self::genesis_internal(keys, config, true, true)
+    }
+
+    // `genesis` without overlapping the circuit building with the proving, as the reference that
+    // `genesis` is bit-identical to (with a config without zero knowledge), and for benchmarking the
+    // overlapping with examples/bench-genesis.rs
+    pub fn genesis_sequential(
+        keys: [HashOut<F>; S],
+        config: CircuitConfig,
+    ) -> Result<(Self, ClockCircuit<S>), ClockError> {
+        Self::genesis_internal(keys, config, false, false)
+    }
+
+    fn genesis_internal(
+        keys: [HashOut<F>; S],
+        config: CircuitConfig,
+        reveal_index: bool,
+        overlap: bool,
+    ) -> Result<(Self, ClockCircuit<S>), ClockError> {
+        let (proof, circuit) = genesis_proof("Clock", &keys, config, reveal_index, overlap)?;
+        let circuit = ClockCircuit {
+            data: circuit.data,
+            targets: circuit.targets,
+        };
+        Ok((Self { proof }, circuit))
+    }
 
-        assert!(clock.counters().all(|counter| counter == 0));
-        Ok((clock, inner_circuit))
+    // INSECURE, for tests only
+    // `genesis` with a config of few FRI query rounds and no proof of work, which provides nowhere
+    // near the security of the standard config, in exchange of much smaller recursive verification
+    // and faster proving. clocks and circuits derived from the returned ones must stay with the
+    // same config i.e. `insecure_test_config`
+    #[cfg(any(test, feature = "insecure-fast-genesis"))]
+    pub fn genesis_fast(keys: [HashOut<F>; S]) -> Result<(Self, ClockCircuit<S>), ClockError> {
+        Self::genesis(keys, insecure_test_config())
     }
 
     pub fn with_proof_and_circuit(
@@ -252,114 +956,97 @@ impl<const S: usize> Clock<S> {
         (Self { proof }, ClockCircuit::with_data(data, config))
     }
 
-    fn merge_internal(
+    // merge with `other`, skipping the proving if either clock dominates the other one
+    pub fn merge(&self, other: &Self, circuit: &ClockCircuit<S>) -> Result<Self, ClockError> {
+        let proof = merge_or_skip(self.view(), other.view(), circuit.view())?;
+        Ok(Self { proof })
+    }
+
+    pub fn update(
         &self,
+        index: usize,
+        secret: F,
         other: &Self,
         circuit: &ClockCircuit<S>,
-        inner_circuit: &ClockCircuit<S>,
-    ) -> anyhow::Result<Self> {
-        let clock1 = self;
-        let clock2 = other;
-        let mut pw = PartialWitness::new();
-        let targets = circuit.targets.as_ref().unwrap();
-        pw.set_proof_with_pis_target(&targets.proof1, &clock1.proof);
-        pw.set_verifier_data_target(&targets.verifier_data1, &inner_circuit.data.verifier_only);
-        pw.set_proof_with_pis_target(&targets.proof2, &clock2.proof);
-        pw.set_verifier_data_target(&targets.verifier_data2, &inner_circuit.data.verifier_only);
-        pw.set_target(targets.updated_index, F::from_canonical_usize(S + 1));
-        pw.set_target(targets.updated_counter, F::from_canonical_u32(u32::MAX));
-        // let msg = Secp256K1Scalar::from_canonical_u32(u32::MAX);
-        // let sig = sign_message(msg, DUMMY_SECRET);
-        pw.set_target(targets.sig, DUMMY_SECRET);
-
-        let mut timing =
-            TimingTree::new("prove merge", "INFO".parse().map_err(anyhow::Error::msg)?);
-        let proof = prove(
-            &circuit.data.prover_only,
-            &circuit.data.common,
-            pw,
-            &mut timing,
+    ) -> Result<Self, ClockError> {
+        let proof = update_proof(
+            self.view(),
+            Some(other.view()),
+            index,
+            secret,
+            circuit.view(),
         )?;
-        timing.print();
-
-        let clock = Self {
-            proof,
-            // depth: self.depth.max(other.depth),
-        };
-        assert!(clock
-            .counters()
-            .zip(clock1.counters())
-            .zip(clock2.counters())
-            .all(|((output_counter, input_counter1), input_counter2)| {
-                output_counter == input_counter1.max(input_counter2)
-            }));
-        Ok(clock)
+        Ok(Self { proof })
     }
 
-    pub fn update(
+    // update without merging another clock, with the second input proof of the circuit disabled
+    pub fn increment(
         &self,
         index: usize,
         secret: F,
-        other: &Self,
         circuit: &ClockCircuit<S>,
-    ) -> anyhow::Result<Self> {
-        let counter = self
-            .counters()
-            .nth(index)
-            .ok_or(anyhow::anyhow!("out of bound index {index}"))?
-            .max(
-                other
-                    .counters()
-                    .nth(index)
-                    .ok_or(anyhow::anyhow!("out of bound index {index}"))?,
-            )
-            + 1;
-        let clock1 = self;
-        let clock2 = other;
-        let inner_circuit = circuit;
-        let mut pw = PartialWitness::new();
-        let targets = circuit.targets.as_ref().unwrap();
-        pw.set_proof_with_pis_target(&targets.proof1, &clock1.proof);
-        pw.set_verifier_data_target(&targets.verifier_data1, &inner_circuit.data.verifier_only);
-        pw.set_proof_with_pis_target(&targets.proof2, &clock2.proof);
-        pw.set_verifier_data_target(&targets.verifier_data2, &inner_circuit.data.verifier_only);
-        pw.set_target(targets.updated_index, F::from_canonical_usize(index));
-        pw.set_target(targets.updated_counter, F::from_canonical_u32(counter));
-        // let msg = Secp256K1Scalar::from_canonical_u32(u32::MAX);
-        // let sig = sign_message(msg, DUMMY_SECRET);
-        pw.set_target(targets.sig, secret);
+    ) -> Result<Self, ClockError> {
+        let proof = update_proof(self.view(), None, index, secret, circuit.view())?;
+        Ok(Self { proof })
+    }
 
-        let mut timing =
-            TimingTree::new("prove update", "INFO".parse().map_err(anyhow::Error::msg)?);
-        let proof = prove(
-            &circuit.data.prover_only,
-            &circuit.data.common,
-            pw,
-            &mut timing,
-        )?;
-        timing.print();
+    pub fn verify(&self, circuit: &ClockCircuit<S>) -> Result<(), ClockError> {
+        self.view().verify(circuit.view())
+    }
 
-        let clock = Self {
-            proof,
-            // depth: self.depth.max(other.depth),
-        };
-        assert!(clock
-            .counters()
-            .enumerate()
-            .zip(clock1.counters())
-            .zip(clock2.counters())
-            .all(|(((i, output_counter), input_counter1), input_counter2)| {
-                if i == index {
-                    output_counter == counter
-                } else {
-                    output_counter == input_counter1.max(input_counter2)
-                }
-            }));
-        Ok(clock)
+    // verify the proof, and check that every counter is no less than the ones of `parents`
+    pub fn verify_succeeds(
+        &self,
+        parents: &[&Self],
+        circuit: &ClockCircuit<S>,
+    ) -> anyhow::Result<()> {
+        self.verify(circuit)?;
+        let parents = parents
+            .iter()
+            .map(|parent| parent.view())
+            .collect::<Vec<_>>();
+        self.view().check_succeeds(&parents)
     }
 
-    pub fn verify(&self, circuit: &ClockCircuit<S>) -> anyhow::Result<()> {
-        circuit.data.verify(self.proof.clone()).map_err(Into::into)
+    // check that `history` is a sequence of clocks that each succeeds its predecessor, in addition
+    // to verifying the proof of each clock. every counter is non-decreasing at every step, and
+    // - an update step (with `last_updated_index`) advances the updated counter, while the other
+    //   counters may advance as well by merging the other input clock of `update`
+    // - a merge step (or any step of a circuit without the updated index) advances at least one
+    //   counter, as dominated merges produce no new clock
+    // this detects a history that is spliced from, or reordered across, multiple valid clocks
+    pub fn verify_succession(history: &[Self], circuit: &ClockCircuit<S>) -> anyhow::Result<()> {
+        for clock in history {
+            clock.verify(circuit)?
+        }
+        for (step, window) in history.windows(2).enumerate() {
+            let [prev, clock] = window else {
+                unreachable!()
+            };
+            let mut updated = Vec::new();
+            for (index, (counter, prev_counter)) in clock
+                .snapshot()
+                .into_iter()
+                .zip(prev.snapshot())
+                .enumerate()
+            {
+                anyhow::ensure!(
+                    counter >= prev_counter,
+                    "counter {index} decreases from {prev_counter} to {counter} at step {step}"
+                );
+                if counter != prev_counter {
+                    updated.push(index)
+                }
+            }
+            match clock.last_updated_index() {
+                Some(index) => anyhow::ensure!(
+                    updated.contains(&index),
+                    "updated counter {index} does not advance at step {step}"
+                ),
+                None => anyhow::ensure!(!updated.is_empty(), "no counter advances at step {step}"),
+            }
+        }
+        Ok(())
     }
 }
 
@@ -368,18 +1055,31 @@ pub fn index_secret(index: usize) -> F {
 }
 
 pub fn public_key(secret: F) -> HashOut<F> {
-    hash_n_to_hash_no_pad::<_, PoseidonPermutation<_>>(&[secret])
+    public_key_n([secret])
+}
+
+// the key derivation of secrets that span multiple field elements, which must stay consistent with
+// `public_key_circuit`
+pub fn public_key_n<const N: usize>(secret: [F; N]) -> HashOut<F> {
+    hash_n_to_hash_no_pad::<_, PoseidonPermutation<_>>(&secret)
+}
+
+pub fn public_key_circuit<const N: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    secret: [Target; N],
+) -> HashOutTarget {
+    builder.hash_n_to_hash_no_pad::<PoseidonHash>(secret.to_vec())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::OnceLock;
+    use std::{collections::HashSet, sync::OnceLock};
 
     use super::*;
 
     const S: usize = 4;
     fn genesis_and_circuit() -> (Clock<S>, ClockCircuit<S>) {
-        Clock::<S>::genesis(
+        Clock::<S>::genesis_with_updated_index(
             [(); S].map({
                 let mut i = 0;
                 move |()| {
@@ -396,26 +1096,446 @@ mod tests {
     static GENESIS_AND_CIRCUIT: OnceLock<(Clock<S>, ClockCircuit<S>)> = OnceLock::new();
 
     #[test]
-    #[should_panic]
     fn malformed_signature() {
         let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
-        genesis
+        let err = genesis
             .update(0, index_secret(1), genesis, circuit)
-            .unwrap();
+            .unwrap_err();
+        if !cfg!(debug_assertions) {
+            assert!(matches!(err, ClockError::ProvingFailed(_)));
+            return;
+        }
+        assert!(matches!(err, ClockError::WitnessInconsistent(_)));
+        let report = format!("{:?}", anyhow::Error::from(err));
+        assert_eq!(report.matches("inconsistent witness").count(), 1);
     }
 
     #[test]
-    #[should_panic]
-    fn malformed_counters_recursive() {
+    fn malformed_counters_recursive() -> anyhow::Result<()> {
         let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
-        let clock1 = genesis.update(0, index_secret(0), genesis, circuit);
-        let Ok(mut clock1) = clock1 else {
-            return; // to trigger `should_panic` failure
-        };
+        let mut clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
         clock1
             .proof
             .public_inputs
             .clone_from(&genesis.proof.public_inputs);
-        clock1.update(0, index_secret(0), &clock1, circuit).unwrap();
+        let err = clock1
+            .update(0, index_secret(0), &clock1, circuit)
+            .unwrap_err();
+        assert!(matches!(err, ClockError::VerificationFailed(_)));
+        let report = format!("{:?}", anyhow::Error::from(err));
+        assert_eq!(report.matches("verification failed:").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn succession() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
+        let clock2 = clock1.update(1, index_secret(1), &clock1, circuit)?;
+        Clock::verify_succession(&[genesis.clone(), clock1.clone(), clock2.clone()], circuit)?;
+        assert!(Clock::verify_succession(
+            &[clock2.clone(), clock1.clone(), genesis.clone()],
+            circuit
+        )
+        .is_err());
+        assert!(Clock::verify_succession(&[clock1.clone(), clock1.clone()], circuit).is_err());
+
+        // a merge step advances multiple counters, and so does an update that merges
+        let clock3 = genesis.update(2, index_secret(2), genesis, circuit)?;
+        let clock4 = genesis.update(3, index_secret(3), genesis, circuit)?;
+        let merged = clock2.merge(&clock3, circuit)?;
+        assert_eq!(merged.last_updated_index(), None);
+        let clock5 = merged.update(0, index_secret(0), &clock4, circuit)?;
+        Clock::verify_succession(&[genesis.clone(), clock1, clock2, merged, clock5], circuit)?;
+        Ok(())
+    }
+
+    #[test]
+    fn public_input_layout() -> anyhow::Result<()> {
+        let (_, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let layout = circuit.public_input_layout();
+        assert_eq!(layout[0], ("counters", 0..S));
+        assert_eq!(layout[1], ("updated_index", S..S + 1));
+        let mut offset = 0;
+        for (_, range) in &layout {
+            assert_eq!(range.start, offset);
+            offset = range.end
+        }
+        assert_eq!(offset, circuit.data.common.num_public_inputs);
+
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        let circuit = ClockCircuit::new(circuit, &keys, CircuitConfig::standard_ecc_config())?;
+        assert_eq!(circuit.public_input_layout(), [("counters", 0..S)]);
+        assert_eq!(circuit.data.common.num_public_inputs, S);
+        Ok(())
+    }
+
+    #[test]
+    fn increment() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.increment(0, index_secret(0), circuit)?;
+        clock1.verify(circuit)?;
+        let clock2 = clock1.increment(0, index_secret(0), circuit)?;
+        clock2.verify(circuit)?;
+        assert_eq!(clock2.counters().collect::<Vec<_>>(), [2, 0, 0, 0]);
+        assert!(matches!(
+            clock2.increment(S, index_secret(S), circuit),
+            Err(ClockError::OutOfBoundsIndex(S))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn bytes_round_trip() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(1, index_secret(1), genesis, circuit)?;
+        for clock2 in [
+            Clock::from_bytes(&clock.to_bytes(), circuit)?,
+            Clock::from_compressed_bytes(&clock.to_compressed_bytes(circuit)?, circuit)?,
+        ] {
+            assert!(clock2.counters().eq(clock.counters()));
+            clock2.verify(circuit)?
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn serde_round_trip() -> anyhow::Result<()> {
+        use serde::de::DeserializeSeed as _;
+
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(1, index_secret(1), genesis, circuit)?;
+        let json = serde_json::to_string(&clock)?;
+        let clock2 =
+            ser::ClockSeed(circuit).deserialize(&mut serde_json::Deserializer::from_str(&json))?;
+        assert_eq!(clock2.proof, clock.proof);
+        clock2.verify(circuit)?;
+        Ok(())
+    }
+
+    #[test]
+    fn last_updated_index() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        assert_eq!(genesis.last_updated_index(), None);
+        let clock = genesis.update(2, index_secret(2), genesis, circuit)?;
+        assert_eq!(clock.last_updated_index(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn check_update_authorized() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        assert!(genesis.check_update_authorized(&keys, &[]));
+        let clock = genesis.update(2, index_secret(2), genesis, circuit)?;
+        assert!(clock.check_update_authorized(&keys, &keys[1..3]));
+        assert!(!clock.check_update_authorized(&keys, &keys[..2]));
+        Ok(())
+    }
+
+    #[test]
+    fn eq_ignores_proof() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
+        let clock1 = clock1.update(1, index_secret(1), &clock1, circuit)?;
+        let clock2 = genesis.update(1, index_secret(1), genesis, circuit)?;
+        let clock2 = clock2.update(0, index_secret(0), &clock2, circuit)?;
+        assert_ne!(clock1.proof, clock2.proof);
+        assert_eq!(clock1, clock2);
+        assert_ne!(&clock1, genesis);
+        assert_eq!(HashSet::from([clock1, clock2]).len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn verifier_only() -> anyhow::Result<()> {
+        let genesis_circuit = ClockCircuit::<S>::new_genesis(CircuitConfig::standard_ecc_config());
+        let genesis = Clock {
+            proof: genesis_circuit.data.prove(PartialWitness::new())?,
+        };
+        let circuit = ClockCircuit::<S>::verifier_only(genesis_circuit.data);
+        genesis.verify(&circuit)?;
+        assert!(matches!(
+            genesis.update(0, index_secret(0), &genesis, &circuit),
+            Err(ClockError::InvalidCircuit(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn rate_limit() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let mut policy = rate::MinGap::new(std::time::Duration::from_secs(3600));
+        let clock =
+            genesis.update_with_policy(0, index_secret(0), genesis, circuit, &mut policy)?;
+        assert!(clock
+            .update_with_policy(0, index_secret(0), &clock, circuit, &mut policy)
+            .is_err());
+        clock.update_with_policy(1, index_secret(1), &clock, circuit, &mut policy)?;
+        Ok(())
+    }
+
+    #[test]
+    fn fri_config() {
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        let mut weak = CircuitConfig::standard_ecc_config();
+        weak.fri_config.num_query_rounds = 1;
+        assert!(matches!(
+            Clock::<S>::genesis(keys, weak),
+            Err(ClockError::InvalidCircuit(_))
+        ));
+        // the fixture and `insecure_test_config` are checked on every genesis of the other tests
+    }
+
+    #[test]
+    fn genesis_overlapped() -> anyhow::Result<()> {
+        // without zero knowledge the proving is deterministic
+        let config = insecure_test_config();
+        assert!(!config.zero_knowledge);
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        let (clock1, circuit1) = Clock::<S>::genesis(keys, config.clone())?;
+        let (clock2, circuit2) = Clock::<S>::genesis_sequential(keys, config)?;
+        assert_eq!(clock1.to_bytes(), clock2.to_bytes());
+        assert_eq!(
+            circuit1.data.verifier_only.circuit_digest,
+            circuit2.data.verifier_only.circuit_digest
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn genesis_fast() -> anyhow::Result<()> {
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        let (genesis, circuit) = Clock::<S>::genesis_fast(keys)?;
+        assert!(genesis.counters().all(|counter| counter == 0));
+        let clock = genesis.update(3, index_secret(3), &genesis, &circuit)?;
+        clock.verify(&circuit)?;
+        assert_eq!(clock.snapshot(), [0, 0, 0, 1]);
+        // the updated index is not revealed by default
+        assert_eq!(clock.proof.public_inputs.len(), S);
+        assert_eq!(clock.last_updated_index(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn saturated_counter() {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let mut clock = genesis.clone();
+        clock.proof.public_inputs[0] = F::from_canonical_u32(u32::MAX);
+        assert!(matches!(
+            clock.update(0, index_secret(0), genesis, circuit),
+            Err(ClockError::CounterSaturated(0))
+        ))
+    }
+
+    #[test]
+    fn sparse() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(1, index_secret(1), genesis, circuit)?;
+        let sparse = clock.to_sparse()?;
+        assert_eq!(sparse.counters, [(1, 1)]);
+        let mut short = clock.clone();
+        short.proof.public_inputs.truncate(S - 1);
+        assert!(short.to_sparse().is_err());
+        sparse.verify(circuit)?;
+        assert!(sparse.to_dense()?.counters().eq(clock.counters()));
+        let mut omitted = sparse.clone();
+        omitted.counters.clear();
+        assert!(omitted.verify(circuit).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn merge() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
+        let clock2 = genesis.update(1, index_secret(1), genesis, circuit)?;
+        assert_eq!(genesis.partial_cmp_causal(&clock1), Some(Ordering::Less));
+        assert_eq!(clock1.partial_cmp_causal(&clock2), None);
+        // dominated merges reuse the existing proof
+        assert_eq!(
+            genesis.merge(&clock1, circuit)?.to_bytes(),
+            clock1.to_bytes()
+        );
+        assert_eq!(
+            clock1.merge(genesis, circuit)?.to_bytes(),
+            clock1.to_bytes()
+        );
+        let clock = clock1.merge(&clock2, circuit)?;
+        clock.verify(circuit)?;
+        assert_eq!(clock.counters().collect::<Vec<_>>(), [1, 1, 0, 0]);
+        // inflated counters that dominate without a valid proof
+        let mut tampered = clock1.clone();
+        tampered.proof.public_inputs[1] = F::ONE;
+        assert!(matches!(
+            genesis.merge(&tampered, circuit),
+            Err(ClockError::VerificationFailed(_))
+        ));
+        assert!(matches!(
+            tampered.merge(genesis, circuit),
+            Err(ClockError::VerificationFailed(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn verify_batch() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(0, index_secret(0), genesis, circuit)?;
+        circuit.verify_batch(&[genesis, &clock])?;
+        let mut malformed = clock.clone();
+        malformed
+            .proof
+            .public_inputs
+            .clone_from(&genesis.proof.public_inputs);
+        let err = circuit
+            .verify_batch(&[genesis, &malformed, &clock])
+            .unwrap_err();
+        assert!(err.to_string().contains("clock 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn threshold() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(3, index_secret(3), genesis, circuit)?;
+        let clock = clock.update(3, index_secret(3), &clock, circuit)?;
+        let threshold_circuit =
+            threshold::ThresholdCircuit::new(circuit, CircuitConfig::standard_ecc_config());
+        assert!(threshold_circuit.data.common.config.zero_knowledge);
+        let proof = clock.prove_threshold(3, 2, &threshold_circuit)?;
+        assert!(threshold_circuit.verify(&proof, 2)?);
+        assert!(threshold_circuit.verify(&proof, 1).is_err());
+        let proof = clock.prove_threshold(3, 3, &threshold_circuit)?;
+        assert!(!threshold_circuit.verify(&proof, 3)?);
+        Ok(())
+    }
+
+    #[test]
+    fn public_key_consistency() -> anyhow::Result<()> {
+        let secret = [0, 1, 2, 3].map(index_secret);
+        let mut builder = CircuitBuilder::<F, D>::new(CircuitConfig::standard_recursion_config());
+        let secret_targets = builder.add_virtual_target_arr::<4>();
+        let key = public_key_circuit(&mut builder, secret_targets);
+        let expected_key = builder.constant_hash(public_key_n(secret));
+        builder.connect_hashes(key, expected_key);
+        let data = builder.build::<C>();
+        let mut pw = PartialWitness::new();
+        pw.set_target_arr(&secret_targets, &secret);
+        data.verify(data.prove(pw)?)
+    }
+
+    #[test]
+    fn diff() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
+        let clock2 = clock1.update(2, index_secret(2), &clock1, circuit)?;
+        let clock2 = clock2.update(2, index_secret(2), &clock2, circuit)?;
+        assert_eq!(genesis.diff(&clock2), [(0, 1), (2, 2)]);
+        assert_eq!(clock1.diff(&clock2), [(2, 2)]);
+        assert!(clock2.diff(&clock1).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn join_counters() {
+        let (genesis, _) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        // off-circuit, so the counters can be set without proving
+        let clocks = [[3, 0, 1, 0], [1, 2, 0, 0], [0, 1, 4, 0]].map(|counters| {
+            let mut clock = genesis.clone();
+            for (input, counter) in clock.proof.public_inputs.iter_mut().zip(counters) {
+                *input = F::from_canonical_u32(counter)
+            }
+            clock
+        });
+        assert_eq!(Clock::join_counters(&clocks.each_ref()), [3, 2, 4, 0]);
+        assert_eq!(Clock::<S>::join_counters(&[]), [0; S]);
+    }
+
+    #[test]
+    fn snapshot() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock = genesis.update(1, index_secret(1), genesis, circuit)?;
+        assert_eq!(
+            clock.snapshot().to_vec(),
+            clock.counters().collect::<Vec<_>>()
+        );
+        assert_eq!(format!("{clock:?}"), "Clock {1: 1}");
+        Ok(())
+    }
+
+    #[test]
+    fn proving_pool() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let pool = pool::ProvingPool::new(None)?;
+        let receivers = (0..S)
+            .map(|index| {
+                pool.submit(move || genesis.update(index, index_secret(index), genesis, circuit))
+            })
+            .collect::<Vec<_>>();
+        for (index, receiver) in receivers.into_iter().enumerate() {
+            let clock = receiver.recv()??;
+            assert_eq!(clock.last_updated_index(), Some(index))
+        }
+        // a panicking job does not take down the pool
+        assert!(pool.submit::<()>(|| panic!("job panic")).recv().is_err());
+        pool.submit(move || genesis.update(0, index_secret(0), genesis, circuit))
+            .recv()??;
+        assert!(pool::ProvingPool::new(Some(0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn merge_many() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let clock1 = genesis.update(0, index_secret(0), genesis, circuit)?;
+        let clock2 = genesis.update(1, index_secret(1), genesis, circuit)?;
+        let clock3 = clock2.update(1, index_secret(1), &clock2, circuit)?;
+        let merge_circuit =
+            merge::MergeCircuit::new(circuit, 4, CircuitConfig::standard_ecc_config())?;
+        let clock = clock1.merge_many(&[&clock2, &clock3], &merge_circuit)?;
+        clock.verify(&merge_circuit)?;
+        assert_eq!(clock.snapshot(), [1, 2, 0, 0]);
+        assert_eq!(format!("{clock:?}"), "MergedClock {0: 1, 1: 2}");
+        let mut tampered = clock3.clone();
+        tampered.proof.public_inputs[2] = F::ONE;
+        assert!(clock1.merge_many(&[&tampered], &merge_circuit).is_err());
+        assert!(
+            merge::MergeCircuit::new(circuit, 1, CircuitConfig::standard_ecc_config()).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_context() -> anyhow::Result<()> {
+        let (genesis, circuit) = GENESIS_AND_CIRCUIT.get_or_init(genesis_and_circuit);
+        let mut clock = genesis.clone();
+        clock.proof.public_inputs[0] = F::from_canonical_u32(5);
+        let Err(ClockError::VerificationFailed(err)) = clock.verify(circuit) else {
+            anyhow::bail!("expect verification failure")
+        };
+        assert!(err.to_string().contains("Clock {0: 5}"));
+        Ok(())
+    }
+
+    #[test]
+    fn zero_knowledge_circuit() -> anyhow::Result<()> {
+        let mut config = CircuitConfig::standard_ecc_config();
+        config.zero_knowledge = true;
+        let keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        let inner = ClockCircuit::<S>::new_genesis(config.clone());
+        let circuit = ClockCircuit::new(&inner, &keys, config)?;
+        assert!(circuit.data.common.config.zero_knowledge);
+        Ok(())
+    }
+
+    #[test]
+    fn dummy_key() {
+        let mut keys = [0, 1, 2, 3].map(|i| public_key(index_secret(i)));
+        keys[2] = dummy_public_key();
+        assert!(matches!(
+            Clock::genesis(keys, CircuitConfig::standard_ecc_config()),
+            Err(ClockError::InvalidCircuit(_))
+        ));
     }
 }
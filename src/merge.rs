@@ -0,0 +1,151 @@
+// merge up to K clocks with one proof, instead of K - 1 chained two-way merges
+// the inner proofs are bound to the verifier data of the clock circuit. the output has the same
+// public input layout as the clock circuit, but it is a proof of this circuit, so it is a
+// `MergedClock` that is verified against `MergeCircuit`, and cannot be fed back into `ClockCircuit`
+// i.e. it is a terminal merge result e.g. for reporting a dependency set
+
+use std::fmt::Debug;
+
+use plonky2::{
+    field::types::Field,
+    iop::witness::{PartialWitness, WitnessWrite},
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, CircuitData, VerifierCircuitData},
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+    },
+};
+use plonky2_u32::gadgets::{
+    arithmetic_u32::U32Target, multiple_comparison::list_le_u32_circuit,
+    range_check::range_check_u32_circuit,
+};
+
+use crate::{check_output_counters, prove_traced, Clock, ClockCircuit, ClockView, C, D, F};
+
+#[derive(Debug)]
+pub struct MergeCircuit<const S: usize> {
+    pub data: CircuitData<F, C, D>,
+    // for verifying the input clocks before proving
+    inner: VerifierCircuitData<F, C, D>,
+    proofs: Vec<ProofWithPublicInputsTarget<D>>,
+}
+
+impl<const S: usize> MergeCircuit<S> {
+    pub fn new(inner: &ClockCircuit<S>, k: usize, config: CircuitConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(k >= 2, "merging {k} clocks");
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let verifier_data = builder.constant_verifier_data(&inner.data.verifier_only);
+        let proofs = (0..k)
+            .map(|_| {
+                let proof = builder.add_virtual_proof_with_pis(&inner.data.common);
+                builder.verify_proof::<C>(&proof, &verifier_data, &inner.data.common);
+                proof
+            })
+            .collect::<Vec<_>>();
+
+        let output_counters = (0..S)
+            .map(|i| {
+                let input_counters = proofs
+                    .iter()
+                    .map(|proof| U32Target(proof.public_inputs[i]))
+                    .collect::<Vec<_>>();
+                range_check_u32_circuit(&mut builder, input_counters.clone());
+                let mut max = input_counters[0];
+                for x in input_counters.into_iter().skip(1) {
+                    let le = list_le_u32_circuit(&mut builder, vec![max], vec![x]);
+                    max = U32Target(builder.select(le, x.0, max.0))
+                }
+                max.0
+            })
+            .collect::<Vec<_>>();
+        builder.register_public_inputs(&output_counters);
+        // the same to the updated index of merging in `ClockCircuit`, if `inner` reveals it
+        if inner.data.common.num_public_inputs > S {
+            let updated_index = builder.constant(F::from_canonical_usize(S + 1));
+            builder.register_public_input(updated_index)
+        }
+        Ok(Self {
+            data: builder.build(),
+            inner: inner.data.verifier_data(),
+            proofs,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct MergedClock<const S: usize> {
+    pub proof: ProofWithPublicInputs<F, C, D>,
+}
+
+impl<const S: usize> Debug for MergedClock<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.view(), f)
+    }
+}
+
+impl<const S: usize> MergedClock<S> {
+    fn view(&self) -> ClockView<'_> {
+        ClockView {
+            name: "MergedClock",
+            s: S,
+            proof: &self.proof,
+        }
+    }
+
+    pub fn counters(&self) -> impl Iterator<Item = u32> + '_ {
+        self.view().counters()
+    }
+
+    pub fn snapshot(&self) -> [u32; S] {
+        let mut snapshot = [0; S];
+        for (snapshot_counter, counter) in snapshot.iter_mut().zip(self.counters()) {
+            *snapshot_counter = counter
+        }
+        snapshot
+    }
+
+    pub fn verify(&self, circuit: &MergeCircuit<S>) -> anyhow::Result<()> {
+        circuit.data.verify(self.proof.clone())
+    }
+}
+
+impl<const S: usize> Clock<S> {
+    // `circuit` is built upon the circuit that proves `self` and `others`
+    pub fn merge_many(
+        &self,
+        others: &[&Self],
+        circuit: &MergeCircuit<S>,
+    ) -> anyhow::Result<MergedClock<S>> {
+        anyhow::ensure!(
+            others.len() < circuit.proofs.len(),
+            "merging {} clocks with circuit of {} inputs",
+            others.len() + 1,
+            circuit.proofs.len()
+        );
+        let mut clocks = vec![self];
+        clocks.extend(others);
+        // a malformed input proof panics inside `prove`, same as `Clock::merge`
+        for (index, clock) in clocks.iter().enumerate() {
+            circuit
+                .inner
+                .verify(clock.proof.clone())
+                .map_err(|err| anyhow::anyhow!("input clock {index}: {err}"))?
+        }
+        let mut pw = PartialWitness::new();
+        for (i, target) in circuit.proofs.iter().enumerate() {
+            // pad with `self`, which does not affect the max
+            pw.set_proof_with_pis_target(target, &clocks.get(i).unwrap_or(&self).proof)
+        }
+        let proof = prove_traced("merge many", S, None, &circuit.data, pw)?;
+        let clock = MergedClock { proof };
+        clock.verify(circuit)?;
+        let joined = Self::join_counters(&clocks);
+        check_output_counters(
+            clock.counters(),
+            joined.into_iter(),
+            joined.into_iter(),
+            None,
+        )?;
+        Ok(clock)
+    }
+}
@@ -0,0 +1,63 @@
+// run proving jobs on a dedicated, bounded thread pool instead of the calling thread
+// plonky2 parallelizes with the rayon pool that the proving runs in, so the proving of concurrent
+// jobs shares the threads of this pool and does not contend with the global pool
+
+use std::{
+    num::NonZeroUsize,
+    sync::{mpsc, Arc},
+    thread::available_parallelism,
+};
+
+use plonky2_maybe_rayon::rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::{panic_message, Clock, ClockCircuit, ClockError, F};
+
+#[derive(Debug)]
+pub struct ProvingPool {
+    pool: ThreadPool,
+}
+
+impl ProvingPool {
+    // default to leave one core for the caller
+    // zero threads is rejected, instead of being taken by rayon as the default number of threads
+    pub fn new(num_thread: Option<usize>) -> anyhow::Result<Self> {
+        let num_thread = match num_thread {
+            Some(num_thread) => num_thread,
+            None => available_parallelism().map_or(1, NonZeroUsize::get).max(2) - 1,
+        };
+        anyhow::ensure!(num_thread > 0, "zero proving threads");
+        Ok(Self {
+            pool: ThreadPoolBuilder::new()
+                .num_threads(num_thread)
+                // without a handler rayon aborts the process on a panicking job
+                .panic_handler(|err| {
+                    tracing::error!("proving job panicked: {}", panic_message(&*err))
+                })
+                .build()?,
+        })
+    }
+
+    pub fn submit<T: Send + 'static>(
+        &self,
+        job: impl FnOnce() -> T + Send + 'static,
+    ) -> mpsc::Receiver<T> {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        self.pool.spawn(move || {
+            // the result is discarded if the receiver has been dropped
+            // if the job panics, the sender is dropped without sending, and the receiver gets error
+            let _ = sender.send(job());
+        });
+        receiver
+    }
+
+    pub fn update<const S: usize>(
+        &self,
+        clock: Clock<S>,
+        index: usize,
+        secret: F,
+        other: Clock<S>,
+        circuit: Arc<ClockCircuit<S>>,
+    ) -> mpsc::Receiver<Result<Clock<S>, ClockError>> {
+        self.submit(move || clock.update(index, secret, &other, &circuit))
+    }
+}
@@ -0,0 +1,78 @@
+// reject too frequent updates of the same index before spending the proving effort on them
+// the policy is local to the updating node and not enforced by the circuit, i.e. a verifier cannot
+// tell whether a clock has been updated under any policy
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{Clock, ClockCircuit, F};
+
+pub trait UpdatePolicy {
+    // called before proving
+    fn check(&self, index: usize) -> anyhow::Result<()>;
+
+    // called after the updated clock is proved
+    fn record(&mut self, index: usize);
+}
+
+#[derive(Debug, Default)]
+pub struct Unrestricted;
+
+impl UpdatePolicy for Unrestricted {
+    fn check(&self, _: usize) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn record(&mut self, _: usize) {}
+}
+
+#[derive(Debug)]
+pub struct MinGap {
+    pub gap: Duration,
+    last_update: HashMap<usize, Instant>,
+}
+
+impl MinGap {
+    pub fn new(gap: Duration) -> Self {
+        Self {
+            gap,
+            last_update: Default::default(),
+        }
+    }
+}
+
+impl UpdatePolicy for MinGap {
+    fn check(&self, index: usize) -> anyhow::Result<()> {
+        if let Some(last_update) = self.last_update.get(&index) {
+            let elapsed = last_update.elapsed();
+            anyhow::ensure!(
+                elapsed >= self.gap,
+                "index {index} updated {elapsed:?} ago, less than {:?}",
+                self.gap
+            )
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, index: usize) {
+        self.last_update.insert(index, Instant::now());
+    }
+}
+
+impl<const S: usize> Clock<S> {
+    pub fn update_with_policy(
+        &self,
+        index: usize,
+        secret: F,
+        other: &Self,
+        circuit: &ClockCircuit<S>,
+        policy: &mut impl UpdatePolicy,
+    ) -> anyhow::Result<Self> {
+        policy.check(index)?;
+        let clock = self.update(index, secret, other, circuit)?;
+        policy.record(index);
+        Ok(clock)
+    }
+}
@@ -72,7 +72,10 @@ use plonky2::util::serialization::Write;
 // }
 
 use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::proof::{CompressedProofWithPublicInputs, ProofWithPublicInputs};
 use plonky2::util::serialization::WitnessGeneratorSerializer;
+use serde::de::{DeserializeSeed, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 // use crate::ClockCircuit;
 
@@ -376,6 +379,35 @@ impl<const S: usize> crate::Clock<S> {
         self.proof.to_bytes()
     }
 
+    pub fn from_bytes(bytes: &[u8], circuit: &crate::ClockCircuit<S>) -> anyhow::Result<Self> {
+        Ok(Self {
+            proof: ProofWithPublicInputs::from_bytes(bytes.to_vec(), &circuit.data.common)?,
+        })
+    }
+
+    // compressed encoding, which requires the circuit on both directions
+    pub fn to_compressed_bytes(&self, circuit: &crate::ClockCircuit<S>) -> anyhow::Result<Vec<u8>> {
+        let proof = self.proof.clone().compress(
+            &circuit.data.verifier_only.circuit_digest,
+            &circuit.data.common,
+        )?;
+        Ok(proof.to_bytes())
+    }
+
+    pub fn from_compressed_bytes(
+        bytes: &[u8],
+        circuit: &crate::ClockCircuit<S>,
+    ) -> anyhow::Result<Self> {
+        let proof =
+            CompressedProofWithPublicInputs::from_bytes(bytes.to_vec(), &circuit.data.common)?;
+        Ok(Self {
+            proof: proof.decompress(
+                &circuit.data.verifier_only.circuit_digest,
+                &circuit.data.common,
+            )?,
+        })
+    }
+
     // pub fn from_bytes(
     //     clock_bytes: Vec<u8>,
     //     circuit_bytes: &[u8],
@@ -393,3 +425,25 @@ impl<const S: usize> crate::Clock<S> {
     //     Ok((clock, ClockCircuit::with_data(data, config)))
     // }
 }
+
+// serde encodes the clock as the bytes of `to_bytes`, and decoding requires the circuit, so there is
+// no `Deserialize` implementation, instead deserialize with the seed `ClockSeed(&circuit)`
+impl<const S: usize> Serialize for crate::Clock<S> {
+    fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+        serializer.serialize_bytes(&self.to_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClockSeed<'a, const S: usize>(pub &'a crate::ClockCircuit<S>);
+
+impl<'de, const S: usize> DeserializeSeed<'de> for ClockSeed<'_, S> {
+    type Value = crate::Clock<S>;
+
+    fn deserialize<T: Deserializer<'de>>(self, deserializer: T) -> Result<Self::Value, T::Error> {
+        // `serialize_bytes` is decoded as a sequence of `u8` by the `Vec` implementation, which fails
+        // on the formats that encode bytes natively, so decode with a bytes visitor
+        let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+        crate::Clock::from_bytes(&bytes, self.0).map_err(T::Error::custom)
+    }
+}
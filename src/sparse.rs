@@ -0,0 +1,67 @@
+// sparse representation of `Clock`, which only carries the nonzero counters
+// each omitted counter saves 8 bytes, which is only significant next to the proof for large `S`
+// the proof commits to the hash of the full public inputs, so verifying against the expanded
+// counters confirms that every omitted counter is genuinely zero, and the circuit needs no
+// dedicated mode for it
+
+use plonky2::{
+    field::types::Field,
+    plonk::proof::{Proof, ProofWithPublicInputs},
+};
+
+use crate::{Clock, ClockCircuit, C, D, F};
+
+#[derive(Debug, Clone)]
+pub struct SparseClock<const S: usize> {
+    // (index, counter) pairs in ascending index order, without zero counters
+    pub counters: Vec<(usize, u32)>,
+    // public inputs following the counters, i.e. the updated index
+    pub trailing_inputs: Vec<F>,
+    pub proof: Proof<F, C, D>,
+}
+
+impl<const S: usize> Clock<S> {
+    pub fn to_sparse(&self) -> anyhow::Result<SparseClock<S>> {
+        anyhow::ensure!(
+            self.proof.public_inputs.len() >= S,
+            "{} public inputs, expected at least {S}",
+            self.proof.public_inputs.len()
+        );
+        Ok(SparseClock {
+            counters: self
+                .counters()
+                .enumerate()
+                .filter(|(_, counter)| *counter != 0)
+                .collect(),
+            trailing_inputs: self.proof.public_inputs[S..].to_vec(),
+            proof: self.proof.proof.clone(),
+        })
+    }
+}
+
+impl<const S: usize> SparseClock<S> {
+    pub fn to_dense(&self) -> anyhow::Result<Clock<S>> {
+        let mut public_inputs = vec![F::ZERO; S];
+        let mut prev_index = None;
+        for &(index, counter) in &self.counters {
+            anyhow::ensure!(index < S, "out of bound index {index}");
+            anyhow::ensure!(
+                prev_index.map_or(true, |prev_index| prev_index < index),
+                "unordered or duplicated index {index}"
+            );
+            prev_index = Some(index);
+            public_inputs[index] = F::from_canonical_u32(counter)
+        }
+        public_inputs.extend(&self.trailing_inputs);
+        Ok(Clock {
+            proof: ProofWithPublicInputs {
+                proof: self.proof.clone(),
+                public_inputs,
+            },
+        })
+    }
+
+    pub fn verify(&self, circuit: &ClockCircuit<S>) -> anyhow::Result<()> {
+        Ok(self.to_dense()?.verify(circuit)?)
+    }
+}
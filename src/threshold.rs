@@ -0,0 +1,104 @@
+// prove that a counter of a clock reaches a threshold, without revealing the counter, its index, or
+// any other counter of the clock
+// the threshold is public along with the result, since the statement is meaningless to a verifier
+// that does not know the threshold
+
+use plonky2::{
+    field::types::{Field, PrimeField64},
+    iop::{
+        target::Target,
+        witness::{PartialWitness, WitnessWrite},
+    },
+    plonk::{
+        circuit_builder::CircuitBuilder,
+        circuit_data::{CircuitConfig, CircuitData},
+        proof::{ProofWithPublicInputs, ProofWithPublicInputsTarget},
+    },
+};
+use plonky2_u32::gadgets::{
+    arithmetic_u32::U32Target, multiple_comparison::list_le_u32_circuit,
+    range_check::range_check_u32_circuit,
+};
+
+use crate::{prove_traced, Clock, ClockCircuit, C, D, F};
+
+#[derive(Debug)]
+pub struct ThresholdCircuit<const S: usize> {
+    pub data: CircuitData<F, C, D>,
+    // public inputs are `threshold` followed by whether the counter reaches it
+    // every target is witness
+    proof: ProofWithPublicInputsTarget<D>,
+    index: Target,
+    threshold: Target,
+}
+
+impl<const S: usize> ThresholdCircuit<S> {
+    // zero knowledge is always enabled regardless of `config`, otherwise the proof may leak the
+    // witness i.e. the hidden counter and index
+    pub fn new(inner: &ClockCircuit<S>, mut config: CircuitConfig) -> Self {
+        config.zero_knowledge = true;
+        let mut builder = CircuitBuilder::<F, D>::new(config);
+        let proof = builder.add_virtual_proof_with_pis(&inner.data.common);
+        // bind to the clock circuit, so the proof cannot come from an arbitrary circuit
+        let verifier_data = builder.constant_verifier_data(&inner.data.verifier_only);
+        builder.verify_proof::<C>(&proof, &verifier_data, &inner.data.common);
+
+        let index = builder.add_virtual_target();
+        let threshold = builder.add_virtual_target();
+        // an out of bound index selects zero
+        let mut counter = builder.zero();
+        for (i, input_counter) in proof.public_inputs[..S].iter().enumerate() {
+            let i = builder.constant(F::from_canonical_usize(i));
+            let is_selected = builder.is_equal(index, i);
+            counter = builder.select(is_selected, *input_counter, counter)
+        }
+        let counter = U32Target(counter);
+        let threshold_u32 = U32Target(threshold);
+        range_check_u32_circuit(&mut builder, vec![counter, threshold_u32]);
+        let reached = list_le_u32_circuit(&mut builder, vec![threshold_u32], vec![counter]);
+
+        builder.register_public_input(threshold);
+        builder.register_public_input(reached.target);
+        Self {
+            data: builder.build(),
+            proof,
+            index,
+            threshold,
+        }
+    }
+
+    // verify the proof is made against `threshold`, and return whether the threshold is reached
+    pub fn verify(
+        &self,
+        proof: &ProofWithPublicInputs<F, C, D>,
+        threshold: u32,
+    ) -> anyhow::Result<bool> {
+        self.data.verify(proof.clone())?;
+        let [proven_threshold, reached] = proof.public_inputs[..] else {
+            anyhow::bail!("unexpected public inputs {:?}", proof.public_inputs)
+        };
+        anyhow::ensure!(
+            proven_threshold.to_canonical_u64() == threshold as u64,
+            "proven against threshold {proven_threshold}, expected {threshold}"
+        );
+        Ok(reached == F::ONE)
+    }
+}
+
+impl<const S: usize> Clock<S> {
+    pub fn prove_threshold(
+        &self,
+        index: usize,
+        threshold: u32,
+        circuit: &ThresholdCircuit<S>,
+    ) -> anyhow::Result<ProofWithPublicInputs<F, C, D>> {
+        anyhow::ensure!(index < S, "out of bound index {index}");
+        let mut pw = PartialWitness::new();
+        pw.set_proof_with_pis_target(&circuit.proof, &self.proof);
+        pw.set_target(circuit.index, F::from_canonical_usize(index));
+        pw.set_target(circuit.threshold, F::from_canonical_u32(threshold));
+
+        // keep the hidden index out of traces as well
+        prove_traced("threshold", S, None, &circuit.data, pw)
+    }
+}